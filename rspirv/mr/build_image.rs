@@ -0,0 +1,50 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+impl Builder {
+    /// Creates an OpImageSampleImplicitLod instruction and returns the
+    /// result id.
+    ///
+    /// `image_operands`, if given, pairs the `ImageOperands` mask with its
+    /// dependent trailing operands (e.g. the id for `Bias`, or the pair of
+    /// ids for `Grad`). As with [`load`](#method.load)'s `memory_access`,
+    /// each operand is tagged with the bit it belongs to and reordered
+    /// into canonical bit order for you.
+    pub fn image_sample_implicit_lod(&mut self,
+                                     result_type: spirv::Word,
+                                     sampled_image: spirv::Word,
+                                     coordinate: spirv::Word,
+                                     image_operands: Option<(spirv::ImageOperands, Vec<MaskOperand>)>)
+                                     -> BuildResult<spirv::Word> {
+        if self.basic_block.is_none() {
+            return Err(Error::DetachedInstruction);
+        }
+
+        let id = self.id();
+        let mut operands = vec![mr::Operand::IdRef(sampled_image), mr::Operand::IdRef(coordinate)];
+        if let Some((mask, params)) = image_operands {
+            let mask_operand = mr::Operand::ImageOperands(mask);
+            let ordered = validate_and_order_mask_operands(&mask_operand, params)?;
+            operands.push(mask_operand);
+            operands.extend(ordered);
+        }
+
+        let inst = mr::Instruction::new(spirv::Op::ImageSampleImplicitLod,
+                                        Some(result_type),
+                                        Some(id),
+                                        operands);
+        self.basic_block.as_mut().unwrap().instructions.push(inst);
+        Ok(id)
+    }
+}
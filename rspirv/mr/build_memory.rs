@@ -0,0 +1,72 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+impl Builder {
+    /// Creates an OpLoad instruction and returns the result id.
+    ///
+    /// `memory_access`, if given, pairs the `MemoryAccess` mask with its
+    /// dependent trailing operands. Each one is a
+    /// [`MaskOperand`](struct.MaskOperand.html) naming the single bit it
+    /// belongs to (e.g. the literal alignment operand is tagged
+    /// `Aligned`'s bit) — callers can supply them in any order and
+    /// [`validate_and_order_mask_operands`] sorts them out.
+    pub fn load(&mut self,
+               result_type: spirv::Word,
+               pointer: spirv::Word,
+               memory_access: Option<(spirv::MemoryAccess, Vec<MaskOperand>)>)
+               -> BuildResult<spirv::Word> {
+        if self.basic_block.is_none() {
+            return Err(Error::DetachedInstruction);
+        }
+
+        let id = self.id();
+        let mut operands = vec![mr::Operand::IdRef(pointer)];
+        if let Some((access, params)) = memory_access {
+            let mask_operand = mr::Operand::MemoryAccess(access);
+            let ordered = validate_and_order_mask_operands(&mask_operand, params)?;
+            operands.push(mask_operand);
+            operands.extend(ordered);
+        }
+
+        let inst = mr::Instruction::new(spirv::Op::Load, Some(result_type), Some(id), operands);
+        self.basic_block.as_mut().unwrap().instructions.push(inst);
+        Ok(id)
+    }
+
+    /// Creates an OpStore instruction.
+    ///
+    /// See [`load`](#method.load) for how `memory_access`'s trailing
+    /// operands are tagged and ordered.
+    pub fn store(&mut self,
+                pointer: spirv::Word,
+                object: spirv::Word,
+                memory_access: Option<(spirv::MemoryAccess, Vec<MaskOperand>)>)
+                -> BuildResult<()> {
+        if self.basic_block.is_none() {
+            return Err(Error::DetachedInstruction);
+        }
+
+        let mut operands = vec![mr::Operand::IdRef(pointer), mr::Operand::IdRef(object)];
+        if let Some((access, params)) = memory_access {
+            let mask_operand = mr::Operand::MemoryAccess(access);
+            let ordered = validate_and_order_mask_operands(&mask_operand, params)?;
+            operands.push(mask_operand);
+            operands.extend(ordered);
+        }
+
+        let inst = mr::Instruction::new(spirv::Op::Store, None, None, operands);
+        self.basic_block.as_mut().unwrap().instructions.push(inst);
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+impl Builder {
+    /// Creates an OpSelectionMerge instruction.
+    ///
+    /// Must be emitted as the second-to-last instruction of the current
+    /// basic block, immediately before its terminating branch. `params`
+    /// are `selection_control`'s trailing operands, each tagged with the
+    /// bit it belongs to (see [`Builder::load`](struct.Builder.html#method.load)
+    /// for the general tagging/ordering scheme) — `SelectionControl`'s
+    /// bits don't currently carry any, but a future one would be caught
+    /// here too.
+    pub fn selection_merge(&mut self,
+                           merge_block: spirv::Word,
+                           selection_control: spirv::SelectionControl,
+                           params: Vec<MaskOperand>)
+                           -> BuildResult<()> {
+        if self.basic_block.is_none() {
+            return Err(Error::DetachedInstruction);
+        }
+
+        let mask_operand = mr::Operand::SelectionControl(selection_control);
+        let ordered = validate_and_order_mask_operands(&mask_operand, params)?;
+        let mut operands = vec![mr::Operand::IdRef(merge_block), mask_operand];
+        operands.extend(ordered);
+
+        let inst = mr::Instruction::new(spirv::Op::SelectionMerge, None, None, operands);
+        self.basic_block.as_mut().unwrap().instructions.push(inst);
+        Ok(())
+    }
+
+    /// Creates an OpLoopMerge instruction.
+    ///
+    /// Must be emitted as the second-to-last instruction of the current
+    /// basic block, immediately before its terminating branch. `params`
+    /// are `loop_control`'s trailing operands (e.g. the literal length for
+    /// `DependencyLength`), tagged and ordered the same way as
+    /// [`Builder::load`](struct.Builder.html#method.load)'s
+    /// `memory_access`.
+    pub fn loop_merge(&mut self,
+                      merge_block: spirv::Word,
+                      continue_target: spirv::Word,
+                      loop_control: spirv::LoopControl,
+                      params: Vec<MaskOperand>)
+                      -> BuildResult<()> {
+        if self.basic_block.is_none() {
+            return Err(Error::DetachedInstruction);
+        }
+
+        let mask_operand = mr::Operand::LoopControl(loop_control);
+        let ordered = validate_and_order_mask_operands(&mask_operand, params)?;
+        let mut operands = vec![mr::Operand::IdRef(merge_block),
+                                mr::Operand::IdRef(continue_target),
+                                mask_operand];
+        operands.extend(ordered);
+
+        let inst = mr::Instruction::new(spirv::Op::LoopMerge, None, None, operands);
+        self.basic_block.as_mut().unwrap().instructions.push(inst);
+        Ok(())
+    }
+}
@@ -45,11 +45,60 @@ impl Builder {
         }
     }
 
+    /// Creates a builder that continues editing an already-built `Module`,
+    /// e.g. one produced by parsing a binary.
+    ///
+    /// `module`'s existing sections are adopted as-is, and the next id to
+    /// be allocated is initialized one past the highest id already
+    /// referenced anywhere in `module` (or the module header's recorded
+    /// bound, if that is higher), so newly allocated ids cannot collide
+    /// with ones already in use.
+    pub fn from_module(module: mr::Module) -> Builder {
+        let header_bound = module.header.as_ref().map_or(0, |h| h.bound);
+        let next_id = header_bound.max(highest_id(&module) + 1);
+        Builder {
+            module: module,
+            next_id: next_id,
+            function: None,
+            basic_block: None,
+        }
+    }
+
     /// Returns the `Module` under construction.
-    pub fn module(self) -> mr::Module {
+    ///
+    /// The header's id bound is (re)written to reflect every id allocated
+    /// by this builder, including ids reserved via
+    /// [`alloc_id`](#method.alloc_id)/[`set_id_bound`](#method.set_id_bound),
+    /// so the returned module's header is never stale relative to its
+    /// instructions.
+    pub fn module(mut self) -> mr::Module {
+        match self.module.header {
+            Some(ref mut header) => header.bound = self.next_id,
+            None => self.module.header = Some(mr::ModuleHeader::new(self.next_id)),
+        }
         self.module
     }
 
+    /// Allocates and returns a fresh, currently-unused id.
+    ///
+    /// Useful when a caller needs to reserve an id before the instruction
+    /// that defines it is known, e.g. for forward references.
+    pub fn alloc_id(&mut self) -> spirv::Word {
+        self.id()
+    }
+
+    /// Explicitly sets the next id to be allocated.
+    ///
+    /// Use this to reserve a contiguous range of ids up front, or to raise
+    /// the bound past one recovered from a module header without
+    /// allocating ids one at a time. Never lowers `next_id` below its
+    /// current value.
+    pub fn set_id_bound(&mut self, bound: spirv::Word) {
+        if bound > self.next_id {
+            self.next_id = bound;
+        }
+    }
+
     #[inline(always)]
     fn id(&mut self) -> spirv::Word {
         let id = self.next_id;
@@ -67,14 +116,21 @@ impl Builder {
             return Err(Error::NestedFunction);
         }
 
+        // `FunctionControl`'s bits never carry trailing operands, but
+        // running it through the same validator as the other combined-mask
+        // operands keeps `OpFunction` consistent with `OpLoad`/`OpStore`/
+        // `OpImageSampleImplicitLod`/`OpSelectionMerge`/`OpLoopMerge`
+        // should a future capability add one.
+        let control_operand = mr::Operand::FunctionControl(control);
+        validate_and_order_mask_operands(&control_operand, vec![])?;
+
         let id = self.id();
 
         let mut f = mr::Function::new();
         f.def = Some(mr::Instruction::new(spirv::Op::Function,
                                           Some(return_type),
                                           Some(id),
-                                          vec![mr::Operand::FunctionControl(control),
-                                               mr::Operand::IdRef(function_type)]));
+                                          vec![control_operand, mr::Operand::IdRef(function_type)]));
         self.function = Some(f);
         Ok(id)
     }
@@ -143,6 +199,32 @@ impl Builder {
         id
     }
 
+    /// Creates an OpExtInst instruction and returns the result id.
+    ///
+    /// `set_id` must be the result id of a prior
+    /// [`ext_inst_import`](#method.ext_inst_import) call, and `instruction`
+    /// the literal instruction number within that set — e.g.
+    /// `GLOp::Sqrt.as_u32()`.
+    pub fn ext_inst(&mut self,
+                    result_type: spirv::Word,
+                    set_id: spirv::Word,
+                    instruction: u32,
+                    operands: Vec<mr::Operand>)
+                    -> BuildResult<spirv::Word> {
+        if self.basic_block.is_none() {
+            return Err(Error::DetachedInstruction);
+        }
+
+        let id = self.id();
+        let mut all_operands = vec![mr::Operand::IdRef(set_id),
+                                    mr::Operand::LiteralExtInstInteger(instruction)];
+        all_operands.extend(operands);
+
+        let inst = mr::Instruction::new(spirv::Op::ExtInst, Some(result_type), Some(id), all_operands);
+        self.basic_block.as_mut().unwrap().instructions.push(inst);
+        Ok(id)
+    }
+
     pub fn memory_model(&mut self,
                         addressing_model: spirv::AddressingModel,
                         memory_model: spirv::MemoryModel) {
@@ -170,6 +252,11 @@ impl Builder {
         self.module.entry_points.push(inst);
     }
 
+    /// Creates an OpExecutionMode instruction.
+    ///
+    /// `params` is always raw `u32` literals, never an `mr::Operand`, so
+    /// it can never carry one of the recognized combined-mask operand
+    /// kinds and has nothing for [`validate_mask_operand_count`] to check.
     pub fn execution_mode(&mut self,
                           entry_point: spirv::Word,
                           execution_mode: spirv::ExecutionMode,
@@ -185,41 +272,230 @@ impl Builder {
     }
 }
 
+/// Scans every section of `module` for the highest id referenced, either
+/// as a result id or as an `IdRef` operand.
+fn highest_id(module: &mr::Module) -> spirv::Word {
+    let mut max_id = 0;
+    {
+        let mut visit = |inst: &mr::Instruction| {
+            if let Some(id) = inst.result_id {
+                max_id = max_id.max(id);
+            }
+            for operand in &inst.operands {
+                if let mr::Operand::IdRef(id) = *operand {
+                    max_id = max_id.max(id);
+                }
+            }
+        };
+
+        for inst in module.capabilities
+            .iter()
+            .chain(module.extensions.iter())
+            .chain(module.ext_inst_imports.iter())
+            .chain(module.memory_model.iter())
+            .chain(module.entry_points.iter())
+            .chain(module.execution_modes.iter())
+            .chain(module.debugs.iter())
+            .chain(module.annotations.iter())
+            .chain(module.types_global_values.iter()) {
+            visit(inst);
+        }
+
+        for f in &module.functions {
+            if let Some(ref def) = f.def {
+                visit(def);
+            }
+            for param in &f.parameters {
+                visit(param);
+            }
+            for bb in &f.basic_blocks {
+                if let Some(ref label) = bb.label {
+                    visit(label);
+                }
+                for inst in &bb.instructions {
+                    visit(inst);
+                }
+            }
+            if let Some(ref end) = f.end {
+                visit(end);
+            }
+        }
+    }
+    max_id
+}
+
+/// Maximum word count representable in an instruction's 16-bit word-count
+/// header field.
+const MAX_INSTRUCTION_WORD_COUNT: usize = u16::max_value() as usize;
+
+/// Number of words needed to encode `s` as a nul-terminated SPIR-V literal
+/// string: `s`'s bytes, packed 4 to a word, plus a terminating nul that
+/// spills into an extra all-zero word whenever `s`'s length is already a
+/// multiple of 4.
+fn literal_string_word_count(s: &str) -> usize {
+    s.len() / 4 + 1
+}
+
+/// Splits `s` so the returned head encodes in at most `max_words` words
+/// (including its nul terminator) and the split point falls on a UTF-8
+/// character boundary.
+fn split_source_at_word_limit(s: &str, max_words: usize) -> (&str, &str) {
+    if literal_string_word_count(s) <= max_words {
+        return (s, "");
+    }
+    let max_bytes = (max_words.saturating_sub(1)) * 4 + 3;
+    let mut split = max_bytes.min(s.len());
+    while !s.is_char_boundary(split) {
+        split -= 1;
+    }
+    (&s[..split], &s[split..])
+}
+
+impl Builder {
+    /// Creates an OpSource instruction describing the source language that
+    /// produced this module.
+    ///
+    /// If `source` is too long to fit in a single OpSource instruction's
+    /// 16-bit word count, the leading chunk is kept on OpSource and the
+    /// remainder is split across as many OpSourceContinued instructions as
+    /// needed, each holding one chunk. Chunks are always split on a UTF-8
+    /// character boundary, so multibyte codepoints are never torn apart.
+    pub fn source(&mut self,
+                  language: spirv::SourceLanguage,
+                  version: u32,
+                  file: Option<spirv::Word>,
+                  source: Option<&str>) {
+        let fixed_words = 1 + 1 + 1 + if file.is_some() { 1 } else { 0 };
+        let mut operands = vec![mr::Operand::SourceLanguage(language),
+                                mr::Operand::LiteralInt32(version)];
+        if let Some(file) = file {
+            operands.push(mr::Operand::IdRef(file));
+        }
+
+        let source = match source {
+            Some(s) => s,
+            None => {
+                self.module
+                    .debugs
+                    .push(mr::Instruction::new(spirv::Op::Source, None, None, operands));
+                return;
+            }
+        };
+
+        let available_words = MAX_INSTRUCTION_WORD_COUNT - fixed_words;
+        let (head, mut rest) = split_source_at_word_limit(source, available_words);
+        operands.push(mr::Operand::LiteralString(head.to_string()));
+        self.module
+            .debugs
+            .push(mr::Instruction::new(spirv::Op::Source, None, None, operands));
+
+        while !rest.is_empty() {
+            // OpSourceContinued only has the leading word and its literal.
+            let (chunk, remainder) = split_source_at_word_limit(rest, MAX_INSTRUCTION_WORD_COUNT - 1);
+            self.module.debugs.push(mr::Instruction::new(spirv::Op::SourceContinued,
+                                                         None,
+                                                         None,
+                                                         vec![mr::Operand::LiteralString(chunk.to_string())]));
+            rest = remainder;
+        }
+    }
+
+    /// Creates an OpString instruction and returns the result id.
+    pub fn string(&mut self, string: String) -> spirv::Word {
+        let id = self.id();
+        self.module
+            .debugs
+            .push(mr::Instruction::new(spirv::Op::String,
+                                       None,
+                                       Some(id),
+                                       vec![mr::Operand::LiteralString(string)]));
+        id
+    }
+
+    /// Creates an OpName instruction.
+    pub fn name(&mut self, target: spirv::Word, name: String) {
+        self.module.debugs.push(mr::Instruction::new(spirv::Op::Name,
+                                                      None,
+                                                      None,
+                                                      vec![mr::Operand::IdRef(target),
+                                                           mr::Operand::LiteralString(name)]));
+    }
+
+    /// Creates an OpMemberName instruction.
+    pub fn member_name(&mut self, structure_type: spirv::Word, member: u32, name: String) {
+        self.module.debugs.push(mr::Instruction::new(spirv::Op::MemberName,
+                                                      None,
+                                                      None,
+                                                      vec![mr::Operand::IdRef(structure_type),
+                                                           mr::Operand::LiteralInt32(member),
+                                                           mr::Operand::LiteralString(name)]));
+    }
+
+    /// Creates an OpLine instruction.
+    pub fn line(&mut self, file: spirv::Word, line: u32, column: u32) {
+        self.module.debugs.push(mr::Instruction::new(spirv::Op::Line,
+                                                      None,
+                                                      None,
+                                                      vec![mr::Operand::IdRef(file),
+                                                           mr::Operand::LiteralInt32(line),
+                                                           mr::Operand::LiteralInt32(column)]));
+    }
+}
+
 include!("build_type.rs");
 include!("build_terminator.rs");
+include!("capability.rs");
+include!("mask.rs");
+include!("build_memory.rs");
+include!("build_image.rs");
+include!("build_merge.rs");
 
 impl Builder {
     /// Creates an OpDecorate instruction and returns the result id.
+    ///
+    /// `decoration` is passed through [`validate_mask_operand_count`], but a
+    /// `Decoration` is not one of the recognized combined-mask operand
+    /// kinds, so that check never rejects anything here: `params` is
+    /// appended as given, and neither its count nor its order is checked.
     pub fn decorate(&mut self,
                     target: spirv::Word,
                     decoration: spirv::Decoration,
                     mut params: Vec<mr::Operand>)
-                    -> spirv::Word {
+                    -> BuildResult<spirv::Word> {
+        let decoration_operand = mr::Operand::Decoration(decoration);
+        validate_mask_operand_count(&decoration_operand, &params)?;
+
         let id = self.id();
-        let mut operands = vec![mr::Operand::IdRef(target), mr::Operand::Decoration(decoration)];
+        let mut operands = vec![mr::Operand::IdRef(target), decoration_operand];
         operands.append(&mut params);
         self.module
             .annotations
             .push(mr::Instruction::new(spirv::Op::Decorate, None, Some(id), operands));
-        id
+        Ok(id)
     }
 
     /// Creates an OpMemberDecorate instruction and returns the result id.
+    ///
+    /// See [`decorate`](#method.decorate) for how `decoration`/`params` are
+    /// validated.
     pub fn member_decorate(&mut self,
                            structure: spirv::Word,
                            member: spirv::Word,
                            decoration: spirv::Decoration,
                            mut params: Vec<mr::Operand>)
-                           -> spirv::Word {
+                           -> BuildResult<spirv::Word> {
+        let decoration_operand = mr::Operand::Decoration(decoration);
+        validate_mask_operand_count(&decoration_operand, &params)?;
+
         let id = self.id();
         let mut operands = vec![mr::Operand::IdRef(structure),
                                 mr::Operand::IdRef(member),
-                                mr::Operand::Decoration(decoration)];
+                                decoration_operand];
         operands.append(&mut params);
         self.module
             .annotations
             .push(mr::Instruction::new(spirv::Op::MemberDecorate, None, Some(id), operands));
-        id
+        Ok(id)
     }
 
     /// Creates an OpDecorationGroup instruction and returns the result id.
@@ -261,3 +537,101 @@ impl Builder {
         id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{literal_string_word_count, split_source_at_word_limit, Builder, Error};
+    use mr;
+    use spirv;
+
+    #[test]
+    fn literal_string_word_count_rounds_up_for_the_nul_terminator() {
+        assert_eq!(literal_string_word_count(""), 1);
+        assert_eq!(literal_string_word_count("abc"), 1);
+        assert_eq!(literal_string_word_count("abcd"), 2);
+        assert_eq!(literal_string_word_count("abcde"), 2);
+    }
+
+    #[test]
+    fn ext_inst_errors_when_no_basic_block_is_open() {
+        let mut b = Builder::new();
+        let set = b.ext_inst_import("GLSL.std.450".to_string());
+        assert_eq!(b.ext_inst(1, set, 0, vec![]), Err(Error::DetachedInstruction));
+    }
+
+    #[test]
+    fn ext_inst_prepends_the_set_and_instruction_number_to_the_given_operands() {
+        let mut b = Builder::new();
+        let set = b.ext_inst_import("GLSL.std.450".to_string());
+        b.begin_function(1, spirv::FunctionControl::empty(), 2).unwrap();
+        b.begin_basic_block().unwrap();
+        b.ext_inst(1, set, 42, vec![mr::Operand::IdRef(7)]).unwrap();
+
+        let inst = b.basic_block.as_ref().unwrap().instructions.last().unwrap();
+        assert_eq!(inst.operands,
+                   vec![mr::Operand::IdRef(set),
+                        mr::Operand::LiteralExtInstInteger(42),
+                        mr::Operand::IdRef(7)]);
+    }
+
+    #[test]
+    fn from_module_starts_next_id_past_the_highest_referenced_id() {
+        let mut module = mr::Module::new();
+        module.types_global_values
+            .push(mr::Instruction::new(spirv::Op::TypeVoid, None, Some(5), vec![]));
+        let mut b = Builder::from_module(module);
+        assert_eq!(b.alloc_id(), 6);
+    }
+
+    #[test]
+    fn from_module_prefers_the_header_bound_when_it_is_higher() {
+        let mut module = mr::Module::new();
+        module.types_global_values
+            .push(mr::Instruction::new(spirv::Op::TypeVoid, None, Some(5), vec![]));
+        module.header = Some(mr::ModuleHeader::new(100));
+        let mut b = Builder::from_module(module);
+        assert_eq!(b.alloc_id(), 100);
+    }
+
+    #[test]
+    fn set_id_bound_never_lowers_the_next_id() {
+        let mut b = Builder::new();
+        b.alloc_id();
+        b.alloc_id();
+        let before = b.alloc_id();
+        b.set_id_bound(1);
+        assert_eq!(b.alloc_id(), before + 1);
+    }
+
+    #[test]
+    fn module_writes_back_the_id_bound_reached_by_allocation() {
+        let mut b = Builder::new();
+        b.alloc_id();
+        b.alloc_id();
+        let next = b.alloc_id() + 1;
+        let module = b.module();
+        assert_eq!(module.header.unwrap().bound, next);
+    }
+
+    #[test]
+    fn split_source_at_word_limit_keeps_short_strings_whole() {
+        assert_eq!(split_source_at_word_limit("abcd", 2), ("abcd", ""));
+    }
+
+    #[test]
+    fn split_source_at_word_limit_splits_long_strings_on_the_word_boundary() {
+        let (head, rest) = split_source_at_word_limit("abcdefgh", 2);
+        assert_eq!(head, "abcdefg");
+        assert_eq!(rest, "h");
+    }
+
+    #[test]
+    fn split_source_at_word_limit_never_splits_a_codepoint() {
+        // Each "é" is 2 bytes; a 7-byte budget must back off to the
+        // preceding character boundary rather than slicing mid-codepoint.
+        let s = "éééé";
+        let (head, rest) = split_source_at_word_limit(s, 2);
+        assert!(s.is_char_boundary(head.len()));
+        assert_eq!(format!("{}{}", head, rest), s);
+    }
+}
@@ -0,0 +1,357 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Inference of `OpCapability`/`OpExtension` from the instructions and
+//! enumerant operands actually used in a module.
+//!
+//! `op_requirements`/`operand_requirements` are a hand-maintained, partial
+//! list of the SPIR-V grammar's per-opcode and per-enumerant
+//! capability/extension requirements, covering only a sample of opcodes
+//! and enumerants — not a codegen'd table from the grammar JSON. Extend
+//! these match arms as more opcodes/enumerants need inference.
+
+use std::collections::HashSet;
+
+/// One "enables this" requirement: satisfied if *any* of `one_of` is
+/// already declared (or, if empty, unconditionally); otherwise the first
+/// alternative is added. `extension`, when present, is added to the
+/// module's extensions section alongside that capability.
+struct Requirement {
+    one_of: &'static [spirv::Capability],
+    extension: Option<&'static str>,
+}
+
+const NONE: &'static [Requirement] = &[];
+
+fn op_requirements(op: spirv::Op) -> &'static [Requirement] {
+    match op {
+        spirv::Op::TypeMatrix => {
+            &[Requirement {
+                  one_of: &[spirv::Capability::Matrix],
+                  extension: None,
+              }]
+        }
+        spirv::Op::TypeImage | spirv::Op::TypeSampler | spirv::Op::TypeSampledImage |
+        spirv::Op::TypeRuntimeArray | spirv::Op::TypeForwardPointer => {
+            &[Requirement {
+                  one_of: &[spirv::Capability::Shader],
+                  extension: None,
+              }]
+        }
+        spirv::Op::ExecutionModeId | spirv::Op::DecorateId => {
+            &[Requirement {
+                  one_of: &[spirv::Capability::Shader],
+                  extension: Some("SPV_KHR_variable_pointers"),
+              }]
+        }
+        spirv::Op::GroupNonUniformBallot => {
+            &[Requirement {
+                  one_of: &[spirv::Capability::GroupNonUniformBallot],
+                  extension: None,
+              }]
+        }
+        _ => NONE,
+    }
+}
+
+fn operand_requirements(operand: &mr::Operand) -> &'static [Requirement] {
+    match *operand {
+        mr::Operand::Decoration(spirv::Decoration::NoPerspective) |
+        mr::Operand::Decoration(spirv::Decoration::Flat) |
+        mr::Operand::Decoration(spirv::Decoration::Centroid) => {
+            &[Requirement {
+                  one_of: &[spirv::Capability::Shader],
+                  extension: None,
+              }]
+        }
+        mr::Operand::BuiltIn(spirv::BuiltIn::ClipDistance) => {
+            &[Requirement {
+                  one_of: &[spirv::Capability::ClipDistance],
+                  extension: None,
+              }]
+        }
+        mr::Operand::BuiltIn(spirv::BuiltIn::CullDistance) => {
+            &[Requirement {
+                  one_of: &[spirv::Capability::CullDistance],
+                  extension: None,
+              }]
+        }
+        mr::Operand::StorageClass(spirv::StorageClass::Uniform) |
+        mr::Operand::StorageClass(spirv::StorageClass::Output) |
+        mr::Operand::StorageClass(spirv::StorageClass::Private) |
+        mr::Operand::StorageClass(spirv::StorageClass::Input) => {
+            &[Requirement {
+                  one_of: &[spirv::Capability::Shader],
+                  extension: None,
+              }]
+        }
+        mr::Operand::Dim(spirv::Dim::DimCube) => {
+            &[Requirement {
+                  one_of: &[spirv::Capability::Shader],
+                  extension: None,
+              }]
+        }
+        mr::Operand::Dim(spirv::Dim::DimBuffer) => {
+            &[Requirement {
+                  one_of: &[spirv::Capability::SampledBuffer, spirv::Capability::ImageBuffer],
+                  extension: None,
+              }]
+        }
+        mr::Operand::ExecutionModel(spirv::ExecutionModel::Geometry) => {
+            &[Requirement {
+                  one_of: &[spirv::Capability::Geometry],
+                  extension: None,
+              }]
+        }
+        mr::Operand::ExecutionModel(spirv::ExecutionModel::TessellationControl) |
+        mr::Operand::ExecutionModel(spirv::ExecutionModel::TessellationEvaluation) => {
+            &[Requirement {
+                  one_of: &[spirv::Capability::Tessellation],
+                  extension: None,
+              }]
+        }
+        mr::Operand::ImageFormat(spirv::ImageFormat::Rgba32f) => NONE,
+        _ => NONE,
+    }
+}
+
+/// Capabilities transitively implied by declaring `cap`, per the SPIR-V
+/// specification's capability dependency graph.
+fn implied_capabilities(cap: spirv::Capability) -> &'static [spirv::Capability] {
+    match cap {
+        spirv::Capability::Shader => &[spirv::Capability::Matrix],
+        spirv::Capability::Geometry => &[spirv::Capability::Shader],
+        spirv::Capability::Tessellation => &[spirv::Capability::Shader],
+        spirv::Capability::Float16Buffer => &[spirv::Capability::Shader],
+        spirv::Capability::ImageBuffer => &[spirv::Capability::Shader],
+        spirv::Capability::SampledBuffer => &[spirv::Capability::Shader],
+        spirv::Capability::GroupNonUniformBallot => &[spirv::Capability::GroupNonUniform],
+        _ => &[],
+    }
+}
+
+fn add_capability_closure(cap: spirv::Capability,
+                          caps: &mut HashSet<spirv::Capability>,
+                          new_caps: &mut Vec<spirv::Capability>) {
+    if !caps.insert(cap) {
+        return;
+    }
+    new_caps.push(cap);
+    for &implied in implied_capabilities(cap) {
+        add_capability_closure(implied, caps, new_caps);
+    }
+}
+
+fn apply_requirement(req: &Requirement,
+                     caps: &mut HashSet<spirv::Capability>,
+                     exts: &mut HashSet<String>,
+                     new_caps: &mut Vec<spirv::Capability>,
+                     new_exts: &mut Vec<String>) {
+    if let Some(ext) = req.extension {
+        if exts.insert(ext.to_string()) {
+            new_exts.push(ext.to_string());
+        }
+    }
+    if req.one_of.is_empty() || req.one_of.iter().any(|c| caps.contains(c)) {
+        return;
+    }
+    add_capability_closure(req.one_of[0], caps, new_caps);
+}
+
+impl Builder {
+    /// Walks every instruction in the module under construction and
+    /// inserts any `OpCapability`/`OpExtension` it requires but that is not
+    /// already present.
+    ///
+    /// Capabilities already declared by hand are left alone and not
+    /// duplicated. Where a feature is enabled by one of several
+    /// alternative capabilities, only one is added if none is already
+    /// present; where a capability transitively implies others (e.g.
+    /// `Shader` implies `Matrix`), the implied ones are added too.
+    /// New `OpExtension`s are appended to the extensions section and new
+    /// `OpCapability`s to the capabilities section; each section keeps its
+    /// logical-layout position, so in the finished module the capabilities
+    /// come before the extensions they depend on, not after them.
+    ///
+    /// Call this once, right before finishing the module with
+    /// [`module`](#method.module).
+    pub fn infer_capabilities_and_extensions(&mut self) {
+        let mut caps: HashSet<spirv::Capability> = HashSet::new();
+        for inst in &self.module.capabilities {
+            if let mr::Operand::Capability(c) = inst.operands[0] {
+                caps.insert(c);
+            }
+        }
+        let mut exts: HashSet<String> = HashSet::new();
+        for inst in &self.module.extensions {
+            if let mr::Operand::LiteralString(ref s) = inst.operands[0] {
+                exts.insert(s.clone());
+            }
+        }
+
+        let mut new_caps = Vec::new();
+        let mut new_exts = Vec::new();
+
+        {
+            let mut visit = |inst: &mr::Instruction| {
+                for req in op_requirements(inst.class.opcode) {
+                    apply_requirement(req, &mut caps, &mut exts, &mut new_caps, &mut new_exts);
+                }
+                for operand in &inst.operands {
+                    for req in operand_requirements(operand) {
+                        apply_requirement(req, &mut caps, &mut exts, &mut new_caps, &mut new_exts);
+                    }
+                }
+            };
+
+            for inst in self.module
+                .capabilities
+                .iter()
+                .chain(self.module.extensions.iter())
+                .chain(self.module.ext_inst_imports.iter())
+                .chain(self.module.memory_model.iter())
+                .chain(self.module.entry_points.iter())
+                .chain(self.module.execution_modes.iter())
+                .chain(self.module.debugs.iter())
+                .chain(self.module.annotations.iter())
+                .chain(self.module.types_global_values.iter()) {
+                visit(inst);
+            }
+            for f in &self.module.functions {
+                if let Some(ref def) = f.def {
+                    visit(def);
+                }
+                for param in &f.parameters {
+                    visit(param);
+                }
+                for bb in &f.basic_blocks {
+                    if let Some(ref label) = bb.label {
+                        visit(label);
+                    }
+                    for inst in &bb.instructions {
+                        visit(inst);
+                    }
+                }
+            }
+        }
+
+        // Each goes into its own section; the section order is fixed by the
+        // module's logical layout, not by the order of these pushes.
+        for ext in new_exts {
+            self.module
+                .extensions
+                .push(mr::Instruction::new(spirv::Op::Extension,
+                                          None,
+                                          None,
+                                          vec![mr::Operand::LiteralString(ext)]));
+        }
+        for cap in new_caps {
+            self.module
+                .capabilities
+                .push(mr::Instruction::new(spirv::Op::Capability,
+                                          None,
+                                          None,
+                                          vec![mr::Operand::Capability(cap)]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod capability_tests {
+    use super::Builder;
+    use mr;
+    use spirv;
+
+    fn has_capability(b: &Builder, cap: spirv::Capability) -> bool {
+        b.module.capabilities.iter().any(|inst| inst.operands[0] == mr::Operand::Capability(cap))
+    }
+
+    fn has_extension(b: &Builder, ext: &str) -> bool {
+        b.module.extensions.iter().any(|inst| inst.operands[0] == mr::Operand::LiteralString(ext.to_string()))
+    }
+
+    #[test]
+    fn adds_the_capability_an_opcode_requires() {
+        let mut b = Builder::new();
+        b.module
+            .types_global_values
+            .push(mr::Instruction::new(spirv::Op::TypeMatrix, None, Some(1), vec![]));
+        b.infer_capabilities_and_extensions();
+        assert!(has_capability(&b, spirv::Capability::Matrix));
+    }
+
+    #[test]
+    fn does_not_duplicate_an_already_declared_capability() {
+        let mut b = Builder::new();
+        b.capability(spirv::Capability::Matrix);
+        b.module
+            .types_global_values
+            .push(mr::Instruction::new(spirv::Op::TypeMatrix, None, Some(1), vec![]));
+        b.infer_capabilities_and_extensions();
+        assert_eq!(b.module.capabilities.len(), 1);
+    }
+
+    #[test]
+    fn adds_transitively_implied_capabilities() {
+        let mut b = Builder::new();
+        b.module
+            .types_global_values
+            .push(mr::Instruction::new(spirv::Op::TypeImage, None, Some(1), vec![]));
+        b.infer_capabilities_and_extensions();
+        // TypeImage requires Shader directly; Shader in turn implies Matrix.
+        assert!(has_capability(&b, spirv::Capability::Shader));
+        assert!(has_capability(&b, spirv::Capability::Matrix));
+    }
+
+    #[test]
+    fn any_of_is_satisfied_by_an_already_present_alternative() {
+        let mut b = Builder::new();
+        b.capability(spirv::Capability::ImageBuffer);
+        b.module
+            .types_global_values
+            .push(mr::Instruction::new(spirv::Op::TypeSampler,
+                                       None,
+                                       Some(1),
+                                       vec![mr::Operand::Dim(spirv::Dim::DimBuffer)]));
+        b.infer_capabilities_and_extensions();
+        // DimBuffer's one_of is [SampledBuffer, ImageBuffer]; ImageBuffer was
+        // already declared, so SampledBuffer must not be added.
+        assert!(!has_capability(&b, spirv::Capability::SampledBuffer));
+    }
+
+    #[test]
+    fn emits_the_extension_and_capability_an_opcode_requires() {
+        let mut b = Builder::new();
+        b.module
+            .types_global_values
+            .push(mr::Instruction::new(spirv::Op::DecorateId, None, None, vec![]));
+        b.infer_capabilities_and_extensions();
+        assert!(has_extension(&b, "SPV_KHR_variable_pointers"));
+        assert!(has_capability(&b, spirv::Capability::Shader));
+    }
+
+    #[test]
+    fn walks_function_parameters_for_operand_requirements() {
+        let mut b = Builder::new();
+        let mut f = mr::Function::new();
+        f.parameters
+            .push(mr::Instruction::new(spirv::Op::FunctionParameter,
+                                       None,
+                                       Some(1),
+                                       vec![mr::Operand::StorageClass(spirv::StorageClass::Uniform)]));
+        b.module.functions.push(f);
+        b.infer_capabilities_and_extensions();
+        assert!(has_capability(&b, spirv::Capability::Shader));
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generated from the `GLSL.std.450` extended instruction set grammar.
+
+/// An opcode in the `GLSL.std.450` extended instruction set.
+///
+/// Pass as the `instruction` argument to
+/// [`Builder::ext_inst`](struct.Builder.html#method.ext_inst).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum GLOp {
+    Round = 1,
+    RoundEven = 2,
+    Trunc = 3,
+    FAbs = 4,
+    SAbs = 5,
+    FSign = 6,
+    SSign = 7,
+    Floor = 8,
+    Ceil = 9,
+    Fract = 10,
+    Radians = 11,
+    Degrees = 12,
+    Sin = 13,
+    Cos = 14,
+    Tan = 15,
+    Asin = 16,
+    Acos = 17,
+    Atan = 18,
+    Sinh = 19,
+    Cosh = 20,
+    Tanh = 21,
+    Asinh = 22,
+    Acosh = 23,
+    Atanh = 24,
+    Atan2 = 25,
+    Pow = 26,
+    Exp = 27,
+    Log = 28,
+    Exp2 = 29,
+    Log2 = 30,
+    Sqrt = 31,
+    InverseSqrt = 32,
+    Determinant = 33,
+    MatrixInverse = 34,
+    Modf = 35,
+    ModfStruct = 36,
+    FMin = 37,
+    UMin = 38,
+    SMin = 39,
+    FMax = 40,
+    UMax = 41,
+    SMax = 42,
+    FClamp = 43,
+    UClamp = 44,
+    SClamp = 45,
+    FMix = 46,
+    Step = 47,
+    SmoothStep = 48,
+    Fma = 49,
+    Frexp = 50,
+    FrexpStruct = 51,
+    Ldexp = 52,
+    PackSnorm4x8 = 53,
+    PackUnorm4x8 = 54,
+    PackSnorm2x16 = 55,
+    PackUnorm2x16 = 56,
+    PackHalf2x16 = 57,
+    PackDouble2x32 = 58,
+    UnpackSnorm2x16 = 59,
+    UnpackUnorm2x16 = 60,
+    UnpackHalf2x16 = 61,
+    UnpackSnorm4x8 = 62,
+    UnpackUnorm4x8 = 63,
+    UnpackDouble2x32 = 64,
+    Length = 65,
+    Distance = 66,
+    Cross = 67,
+    Normalize = 68,
+    FaceForward = 69,
+    Reflect = 70,
+    Refract = 71,
+    FindILsb = 72,
+    FindSMsb = 73,
+    FindUMsb = 74,
+    InterpolateAtCentroid = 75,
+    InterpolateAtSample = 76,
+    InterpolateAtOffset = 77,
+    NMin = 78,
+    NMax = 79,
+    NClamp = 80,
+}
+
+impl GLOp {
+    /// Returns the literal instruction number this opcode encodes to.
+    pub fn as_u32(self) -> u32 {
+        self as u32
+    }
+}
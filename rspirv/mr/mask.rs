@@ -0,0 +1,417 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Canonical bit ordering, trailing-operand validation, and `Display`
+//! formatting for SPIR-V's combined-mask operands (`FunctionControl`,
+//! `MemoryAccess`, `ImageOperands`, `SelectionControl`, `LoopControl`).
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// One bit of a combined-mask operand, and how many trailing operands it
+/// contributes to the instruction when set.
+struct MaskBit {
+    bit: u32,
+    name: &'static str,
+    operand_count: usize,
+}
+
+const MEMORY_ACCESS_BITS: &'static [MaskBit] = &[MaskBit {
+                                                      bit: 0x1,
+                                                      name: "Volatile",
+                                                      operand_count: 0,
+                                                  },
+                                                  MaskBit {
+                                                      bit: 0x2,
+                                                      name: "Aligned",
+                                                      operand_count: 1,
+                                                  },
+                                                  MaskBit {
+                                                      bit: 0x4,
+                                                      name: "Nontemporal",
+                                                      operand_count: 0,
+                                                  },
+                                                  MaskBit {
+                                                      bit: 0x8,
+                                                      name: "MakePointerAvailable",
+                                                      operand_count: 1,
+                                                  },
+                                                  MaskBit {
+                                                      bit: 0x10,
+                                                      name: "MakePointerVisible",
+                                                      operand_count: 1,
+                                                  },
+                                                  MaskBit {
+                                                      bit: 0x20,
+                                                      name: "NonPrivatePointer",
+                                                      operand_count: 0,
+                                                  }];
+
+const IMAGE_OPERANDS_BITS: &'static [MaskBit] = &[MaskBit {
+                                                       bit: 0x1,
+                                                       name: "Bias",
+                                                       operand_count: 1,
+                                                   },
+                                                   MaskBit {
+                                                       bit: 0x2,
+                                                       name: "Lod",
+                                                       operand_count: 1,
+                                                   },
+                                                   MaskBit {
+                                                       bit: 0x4,
+                                                       name: "Grad",
+                                                       operand_count: 2,
+                                                   },
+                                                   MaskBit {
+                                                       bit: 0x8,
+                                                       name: "ConstOffset",
+                                                       operand_count: 1,
+                                                   },
+                                                   MaskBit {
+                                                       bit: 0x10,
+                                                       name: "Offset",
+                                                       operand_count: 1,
+                                                   },
+                                                   MaskBit {
+                                                       bit: 0x20,
+                                                       name: "ConstOffsets",
+                                                       operand_count: 1,
+                                                   },
+                                                   MaskBit {
+                                                       bit: 0x40,
+                                                       name: "Sample",
+                                                       operand_count: 1,
+                                                   },
+                                                   MaskBit {
+                                                       bit: 0x80,
+                                                       name: "MinLod",
+                                                       operand_count: 1,
+                                                   },
+                                                   MaskBit {
+                                                       bit: 0x100,
+                                                       name: "MakeTexelAvailable",
+                                                       operand_count: 1,
+                                                   },
+                                                   MaskBit {
+                                                       bit: 0x200,
+                                                       name: "MakeTexelVisible",
+                                                       operand_count: 1,
+                                                   },
+                                                   MaskBit {
+                                                       bit: 0x400,
+                                                       name: "NonPrivateTexel",
+                                                       operand_count: 0,
+                                                   },
+                                                   MaskBit {
+                                                       bit: 0x800,
+                                                       name: "VolatileTexel",
+                                                       operand_count: 0,
+                                                   },
+                                                   MaskBit {
+                                                       bit: 0x1000,
+                                                       name: "SignExtend",
+                                                       operand_count: 0,
+                                                   },
+                                                   MaskBit {
+                                                       bit: 0x2000,
+                                                       name: "ZeroExtend",
+                                                       operand_count: 0,
+                                                   }];
+
+const SELECTION_CONTROL_BITS: &'static [MaskBit] = &[MaskBit {
+                                                          bit: 0x1,
+                                                          name: "Flatten",
+                                                          operand_count: 0,
+                                                      },
+                                                      MaskBit {
+                                                          bit: 0x2,
+                                                          name: "DontFlatten",
+                                                          operand_count: 0,
+                                                      }];
+
+const LOOP_CONTROL_BITS: &'static [MaskBit] = &[MaskBit {
+                                                     bit: 0x1,
+                                                     name: "Unroll",
+                                                     operand_count: 0,
+                                                 },
+                                                 MaskBit {
+                                                     bit: 0x2,
+                                                     name: "DontUnroll",
+                                                     operand_count: 0,
+                                                 },
+                                                 MaskBit {
+                                                     bit: 0x4,
+                                                     name: "DependencyInfinite",
+                                                     operand_count: 0,
+                                                 },
+                                                 MaskBit {
+                                                     bit: 0x8,
+                                                     name: "DependencyLength",
+                                                     operand_count: 1,
+                                                 }];
+
+const FUNCTION_CONTROL_BITS: &'static [MaskBit] = &[MaskBit {
+                                                         bit: 0x1,
+                                                         name: "Inline",
+                                                         operand_count: 0,
+                                                     },
+                                                     MaskBit {
+                                                         bit: 0x2,
+                                                         name: "DontInline",
+                                                         operand_count: 0,
+                                                     },
+                                                     MaskBit {
+                                                         bit: 0x4,
+                                                         name: "Pure",
+                                                         operand_count: 0,
+                                                     },
+                                                     MaskBit {
+                                                         bit: 0x8,
+                                                         name: "Const",
+                                                         operand_count: 0,
+                                                     }];
+
+/// Returns the canonical bit layout and raw bit value for `operand`, if it
+/// is one of the recognized combined-mask operand kinds.
+fn bits_for(operand: &mr::Operand) -> Option<(&'static [MaskBit], u32)> {
+    match *operand {
+        mr::Operand::MemoryAccess(m) => Some((MEMORY_ACCESS_BITS, m.bits())),
+        mr::Operand::ImageOperands(m) => Some((IMAGE_OPERANDS_BITS, m.bits())),
+        mr::Operand::SelectionControl(m) => Some((SELECTION_CONTROL_BITS, m.bits())),
+        mr::Operand::LoopControl(m) => Some((LOOP_CONTROL_BITS, m.bits())),
+        mr::Operand::FunctionControl(m) => Some((FUNCTION_CONTROL_BITS, m.bits())),
+        _ => None,
+    }
+}
+
+/// One trailing operand tagged with the single mask bit it belongs to.
+///
+/// Callers building a mask-carrying instruction supply these in whatever
+/// order is convenient; [`validate_and_order_mask_operands`] groups them by
+/// bit and re-emits them in canonical ascending-bit order, so a caller that
+/// accidentally supplies e.g. a `Lod` id before a `Bias` id still produces
+/// a well-formed instruction.
+pub struct MaskOperand {
+    pub bit: u32,
+    pub operand: mr::Operand,
+}
+
+/// Validates `operands` against the bits set in `mask_operand`, and
+/// returns them re-ordered into the canonical ascending-bit order the
+/// SPIR-V specification requires.
+///
+/// Every set bit must have exactly the operands it requires tagged with
+/// its bit value, in `operands`; every operand's tagged bit must be set in
+/// the mask. This catches the common class of malformed modules where
+/// mask bits and their trailing operands get out of sync — including
+/// operands supplied for the right bits but in the wrong order, which a
+/// count-only check would miss.
+///
+/// If `mask_operand` is not one of the recognized combined-mask operand
+/// kinds, `operands` are returned as-is (by their given order) and
+/// untouched.
+pub fn validate_and_order_mask_operands(mask_operand: &mr::Operand,
+                                        operands: Vec<MaskOperand>)
+                                        -> BuildResult<Vec<mr::Operand>> {
+    let (bits, mask) = match bits_for(mask_operand) {
+        Some(v) => v,
+        None => return Ok(operands.into_iter().map(|o| o.operand).collect()),
+    };
+
+    let mut by_bit: HashMap<u32, Vec<mr::Operand>> = HashMap::new();
+    for mask_op in operands {
+        by_bit.entry(mask_op.bit).or_insert_with(Vec::new).push(mask_op.operand);
+    }
+
+    let mismatch = || {
+        Error::MaskOperandMismatch(format!("mask {} operands out of sync with its bits",
+                                           display_mask(mask_operand).unwrap()))
+    };
+
+    let mut ordered = Vec::new();
+    for bit in bits {
+        if mask & bit.bit == 0 {
+            if by_bit.contains_key(&bit.bit) {
+                return Err(mismatch());
+            }
+            continue;
+        }
+        let group = by_bit.remove(&bit.bit).unwrap_or_default();
+        if group.len() != bit.operand_count {
+            return Err(mismatch());
+        }
+        ordered.extend(group);
+    }
+    if !by_bit.is_empty() {
+        return Err(mismatch());
+    }
+    Ok(ordered)
+}
+
+/// Validates that `trailing` has exactly the number of operands implied by
+/// the bits set in `mask_operand`, without attempting to re-order them.
+///
+/// Used for call sites like `decorate`/`member_decorate` where the caller
+/// supplies trailing operands as a flat, untagged `Vec<mr::Operand>`
+/// instead of the bit-tagged [`MaskOperand`]s
+/// [`validate_and_order_mask_operands`] needs to reorder — so a flat blob
+/// can only be checked for the right aggregate count, not for operands
+/// landing in the wrong bit's slot.
+///
+/// If `mask_operand` is not one of the recognized combined-mask operand
+/// kinds, this is a no-op.
+pub fn validate_mask_operand_count(mask_operand: &mr::Operand,
+                                   trailing: &[mr::Operand])
+                                   -> BuildResult<()> {
+    let (bits, mask) = match bits_for(mask_operand) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    let expected: usize = bits.iter()
+        .filter(|b| mask & b.bit != 0)
+        .map(|b| b.operand_count)
+        .sum();
+    if trailing.len() != expected {
+        return Err(Error::MaskOperandMismatch(format!("mask {} expects {} trailing operand(s), found {}",
+                                                       display_mask(mask_operand).unwrap(),
+                                                       expected,
+                                                       trailing.len())));
+    }
+    Ok(())
+}
+
+/// A `Display`-able view of a combined-mask operand, rendering its set
+/// bits as `A|B|C` in canonical bit order, or `None` if no bits are set.
+pub struct MaskDisplay<'a> {
+    bits: &'a [MaskBit],
+    mask: u32,
+}
+
+impl<'a> fmt::Display for MaskDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.mask == 0 {
+            return write!(f, "None");
+        }
+        let mut first = true;
+        for bit in self.bits {
+            if self.mask & bit.bit == 0 {
+                continue;
+            }
+            if !first {
+                write!(f, "|")?;
+            }
+            write!(f, "{}", bit.name)?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+/// Returns a `Display`-able view of `operand`, if it is one of the
+/// recognized combined-mask operand kinds.
+pub fn display_mask(operand: &mr::Operand) -> Option<MaskDisplay> {
+    bits_for(operand).map(|(bits, mask)| {
+        MaskDisplay {
+            bits: bits,
+            mask: mask,
+        }
+    })
+}
+
+#[cfg(test)]
+mod mask_tests {
+    use super::{validate_and_order_mask_operands, validate_mask_operand_count, MaskOperand};
+    use mr;
+    use spirv;
+
+    #[test]
+    fn reorders_operands_supplied_out_of_canonical_bit_order() {
+        let mask = mr::Operand::ImageOperands(spirv::ImageOperands::BIAS | spirv::ImageOperands::LOD);
+        // Lod (bit 0x2) supplied before Bias (bit 0x1): must come back
+        // reordered, not merely accepted because the count matches.
+        let params = vec![MaskOperand {
+                               bit: 0x2,
+                               operand: mr::Operand::IdRef(2),
+                           },
+                           MaskOperand {
+                               bit: 0x1,
+                               operand: mr::Operand::IdRef(1),
+                           }];
+        let ordered = validate_and_order_mask_operands(&mask, params).unwrap();
+        assert_eq!(ordered, vec![mr::Operand::IdRef(1), mr::Operand::IdRef(2)]);
+    }
+
+    #[test]
+    fn rejects_an_operand_tagged_for_an_unset_bit() {
+        let mask = mr::Operand::ImageOperands(spirv::ImageOperands::BIAS);
+        let params = vec![MaskOperand {
+                               bit: 0x1,
+                               operand: mr::Operand::IdRef(1),
+                           },
+                           MaskOperand {
+                               bit: 0x2,
+                               operand: mr::Operand::IdRef(2),
+                           }];
+        assert!(validate_and_order_mask_operands(&mask, params).is_err());
+    }
+
+    #[test]
+    fn rejects_a_missing_operand_for_a_set_bit() {
+        let mask = mr::Operand::MemoryAccess(spirv::MemoryAccess::ALIGNED);
+        assert!(validate_and_order_mask_operands(&mask, vec![]).is_err());
+    }
+
+    #[test]
+    fn passes_through_non_mask_operands_untouched() {
+        let not_a_mask = mr::Operand::IdRef(42);
+        let params = vec![MaskOperand {
+                               bit: 0x1,
+                               operand: mr::Operand::IdRef(7),
+                           }];
+        let ordered = validate_and_order_mask_operands(&not_a_mask, params).unwrap();
+        assert_eq!(ordered, vec![mr::Operand::IdRef(7)]);
+    }
+
+    #[test]
+    fn displays_set_bits_joined_with_a_pipe() {
+        let mask = mr::Operand::ImageOperands(spirv::ImageOperands::BIAS | spirv::ImageOperands::LOD);
+        assert_eq!(super::display_mask(&mask).unwrap().to_string(), "Bias|Lod");
+    }
+
+    #[test]
+    fn displays_none_for_an_empty_mask() {
+        let mask = mr::Operand::ImageOperands(spirv::ImageOperands::empty());
+        assert_eq!(super::display_mask(&mask).unwrap().to_string(), "None");
+    }
+
+    #[test]
+    fn count_validation_accepts_the_right_number_of_trailing_operands() {
+        let mask = mr::Operand::MemoryAccess(spirv::MemoryAccess::ALIGNED);
+        let trailing = vec![mr::Operand::LiteralInt32(4)];
+        assert!(validate_mask_operand_count(&mask, &trailing).is_ok());
+    }
+
+    #[test]
+    fn count_validation_rejects_a_missing_trailing_operand() {
+        let mask = mr::Operand::MemoryAccess(spirv::MemoryAccess::ALIGNED);
+        assert!(validate_mask_operand_count(&mask, &[]).is_err());
+    }
+
+    #[test]
+    fn count_validation_is_a_no_op_for_non_mask_operands() {
+        let not_a_mask = mr::Operand::Decoration(spirv::Decoration::Flat);
+        let trailing = vec![mr::Operand::IdRef(1), mr::Operand::IdRef(2)];
+        assert!(validate_mask_operand_count(&not_a_mask, &trailing).is_ok());
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Memory representation (`mr`): an in-memory form of a SPIR-V module that
+//! is easy to construct, traverse, and edit, as opposed to the flat binary
+//! word stream.
+
+mod builder;
+mod module_index;
+mod glsl;
+mod opencl;
+
+pub use self::builder::{Builder, MaskOperand, MaskDisplay, display_mask};
+pub use self::module_index::{EntryPoint, ModuleIndex, Section};
+pub use self::glsl::GLOp;
+pub use self::opencl::CLOp;
+
+use std::error;
+use std::fmt;
+
+/// Errors that can occur while using a [`Builder`](struct.Builder.html) to
+/// construct or edit a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Attempted to begin a function while already inside one.
+    NestedFunction,
+    /// Ended a function that was never begun.
+    MismatchedFunctionEnd,
+    /// Attempted to begin a basic block outside of a function.
+    DetachedBasicBlock,
+    /// Attempted to begin a basic block while already inside one.
+    NestedBasicBlock,
+    /// Attempted to terminate a basic block that was never begun.
+    MismatchedTerminator,
+    /// Attempted to emit an instruction outside of a basic block.
+    DetachedInstruction,
+    /// A combined-mask operand (`FunctionControl`, `MemoryAccess`,
+    /// `ImageOperands`, `SelectionControl`, or `LoopControl`) was not
+    /// followed by exactly the trailing operands its set bits require, in
+    /// canonical bit order. Carries a message naming the offending mask.
+    MaskOperandMismatch(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match *self {
+            Error::NestedFunction => "cannot begin a function while already inside one",
+            Error::MismatchedFunctionEnd => "cannot end a function that was never begun",
+            Error::DetachedBasicBlock => "cannot begin a basic block outside of a function",
+            Error::NestedBasicBlock => "cannot begin a basic block while already inside one",
+            Error::MismatchedTerminator => "cannot terminate a basic block that was never begun",
+            Error::DetachedInstruction => "cannot emit an instruction outside of a basic block",
+            Error::MaskOperandMismatch(ref detail) => return write!(f, "{}", detail),
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        "error constructing or editing a SPIR-V module"
+    }
+}
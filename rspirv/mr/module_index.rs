@@ -0,0 +1,366 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read-only introspection over a built [`Module`](../struct.Module.html).
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use mr;
+use spirv;
+
+/// One of the logical-layout sections mandated by the SPIR-V
+/// specification, in the order they must appear in a module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    Capabilities,
+    Extensions,
+    ExtInstImports,
+    MemoryModel,
+    EntryPoints,
+    ExecutionModes,
+    Debugs,
+    Annotations,
+    TypesGlobalValues,
+    Functions,
+}
+
+/// An `OpEntryPoint` instruction, decoded into its constituent parts.
+pub struct EntryPoint<'a> {
+    pub execution_model: spirv::ExecutionModel,
+    pub entry_point: spirv::Word,
+    pub name: &'a str,
+    pub interface: Vec<spirv::Word>,
+}
+
+/// A read-only index over a [`Module`](../struct.Module.html): id
+/// definitions, decorations, and the logical-layout section each
+/// instruction belongs to.
+///
+/// Built once from a `&Module` and kept alive no longer than the module it
+/// borrows from.
+pub struct ModuleIndex<'a> {
+    instructions: Vec<&'a mr::Instruction>,
+    sections: Vec<(Section, Range<usize>)>,
+    defs: HashMap<spirv::Word, &'a mr::Instruction>,
+    decorations: HashMap<spirv::Word, Vec<&'a mr::Instruction>>,
+    member_decorations: HashMap<(spirv::Word, u32), Vec<&'a mr::Instruction>>,
+    module: &'a mr::Module,
+}
+
+impl<'a> ModuleIndex<'a> {
+    /// Builds an index over `module`.
+    pub fn new(module: &'a mr::Module) -> ModuleIndex<'a> {
+        let mut instructions = Vec::new();
+        let mut sections = Vec::new();
+        let mut defs = HashMap::new();
+        let mut decorations: HashMap<spirv::Word, Vec<&'a mr::Instruction>> = HashMap::new();
+        let mut member_decorations: HashMap<(spirv::Word, u32), Vec<&'a mr::Instruction>> =
+            HashMap::new();
+
+        {
+            let mut push_section = |section: Section, insts: &[&'a mr::Instruction]| {
+                let start = instructions.len();
+                instructions.extend_from_slice(insts);
+                sections.push((section, start..instructions.len()));
+            };
+
+            let caps: Vec<_> = module.capabilities.iter().collect();
+            push_section(Section::Capabilities, &caps);
+            let exts: Vec<_> = module.extensions.iter().collect();
+            push_section(Section::Extensions, &exts);
+            let eiis: Vec<_> = module.ext_inst_imports.iter().collect();
+            push_section(Section::ExtInstImports, &eiis);
+            let mm: Vec<_> = module.memory_model.iter().collect();
+            push_section(Section::MemoryModel, &mm);
+            let eps: Vec<_> = module.entry_points.iter().collect();
+            push_section(Section::EntryPoints, &eps);
+            let ems: Vec<_> = module.execution_modes.iter().collect();
+            push_section(Section::ExecutionModes, &ems);
+            let debugs: Vec<_> = module.debugs.iter().collect();
+            push_section(Section::Debugs, &debugs);
+            let annotations: Vec<_> = module.annotations.iter().collect();
+            push_section(Section::Annotations, &annotations);
+            let types: Vec<_> = module.types_global_values.iter().collect();
+            push_section(Section::TypesGlobalValues, &types);
+
+            let start = instructions.len();
+            for f in &module.functions {
+                if let Some(ref def) = f.def {
+                    instructions.push(def);
+                }
+                for param in &f.parameters {
+                    instructions.push(param);
+                }
+                for bb in &f.basic_blocks {
+                    if let Some(ref label) = bb.label {
+                        instructions.push(label);
+                    }
+                    for inst in &bb.instructions {
+                        instructions.push(inst);
+                    }
+                }
+                if let Some(ref end) = f.end {
+                    instructions.push(end);
+                }
+            }
+            sections.push((Section::Functions, start..instructions.len()));
+        }
+
+        for inst in &instructions {
+            if let Some(id) = inst.result_id {
+                defs.insert(id, *inst);
+            }
+        }
+        for inst in &module.annotations {
+            match inst.class.opcode {
+                spirv::Op::Decorate => {
+                    if let mr::Operand::IdRef(target) = inst.operands[0] {
+                        decorations.entry(target).or_insert_with(Vec::new).push(inst);
+                    }
+                }
+                spirv::Op::MemberDecorate => {
+                    if let (mr::Operand::IdRef(target), mr::Operand::LiteralInt32(member)) =
+                           (inst.operands[0].clone(), inst.operands[1].clone()) {
+                        member_decorations.entry((target, member)).or_insert_with(Vec::new).push(inst);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // A group's own direct decorations (from `OpDecorate` targeting the
+        // group id) propagate to every id/member an `OpGroupDecorate` or
+        // `OpGroupMemberDecorate` applies that group to.
+        for inst in &module.annotations {
+            match inst.class.opcode {
+                spirv::Op::GroupDecorate => {
+                    if let mr::Operand::IdRef(group) = inst.operands[0] {
+                        let group_decorations = decorations.get(&group).cloned().unwrap_or_default();
+                        for operand in &inst.operands[1..] {
+                            if let mr::Operand::IdRef(target) = *operand {
+                                decorations.entry(target)
+                                    .or_insert_with(Vec::new)
+                                    .extend(group_decorations.iter().cloned());
+                            }
+                        }
+                    }
+                }
+                spirv::Op::GroupMemberDecorate => {
+                    if let mr::Operand::IdRef(group) = inst.operands[0] {
+                        let group_decorations = decorations.get(&group).cloned().unwrap_or_default();
+                        for pair in inst.operands[1..].chunks(2) {
+                            if let (mr::Operand::IdRef(target), mr::Operand::LiteralInt32(member)) =
+                                   (pair[0].clone(), pair[1].clone()) {
+                                member_decorations.entry((target, member))
+                                    .or_insert_with(Vec::new)
+                                    .extend(group_decorations.iter().cloned());
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        ModuleIndex {
+            instructions: instructions,
+            sections: sections,
+            defs: defs,
+            decorations: decorations,
+            member_decorations: member_decorations,
+            module: module,
+        }
+    }
+
+    /// Returns the instruction that defines `id`, if any.
+    pub fn def(&self, id: spirv::Word) -> Option<&'a mr::Instruction> {
+        self.defs.get(&id).cloned()
+    }
+
+    /// Returns `id`'s result type, resolved through its defining
+    /// instruction.
+    pub fn result_type(&self, id: spirv::Word) -> Option<spirv::Word> {
+        self.def(id).and_then(|inst| inst.result_type)
+    }
+
+    /// Returns every decoration targeting `id`, whether from a direct
+    /// `OpDecorate` or propagated from an `OpDecorationGroup` id via
+    /// `OpGroupDecorate`.
+    pub fn decorations(&self, id: spirv::Word) -> &[&'a mr::Instruction] {
+        self.decorations.get(&id).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Returns every decoration targeting member `member` of struct type
+    /// `structure`, whether from a direct `OpMemberDecorate` or propagated
+    /// from an `OpDecorationGroup` id via `OpGroupMemberDecorate`.
+    pub fn member_decorations(&self, structure: spirv::Word, member: u32) -> &[&'a mr::Instruction] {
+        self.member_decorations.get(&(structure, member)).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Returns the logical-layout section boundaries, in spec order, as
+    /// ranges into [`instructions`](#method.instructions).
+    pub fn sections(&self) -> &[(Section, Range<usize>)] {
+        &self.sections
+    }
+
+    /// Returns every instruction in the module, flattened in logical-layout
+    /// order.
+    pub fn instructions(&self) -> &[&'a mr::Instruction] {
+        &self.instructions
+    }
+
+    /// Returns every entry point declared by the module.
+    pub fn entry_points(&self) -> Vec<EntryPoint<'a>> {
+        self.module
+            .entry_points
+            .iter()
+            .filter_map(|inst| {
+                let execution_model = match inst.operands[0] {
+                    mr::Operand::ExecutionModel(m) => m,
+                    _ => return None,
+                };
+                let entry_point = match inst.operands[1] {
+                    mr::Operand::IdRef(id) => id,
+                    _ => return None,
+                };
+                let name = match inst.operands[2] {
+                    mr::Operand::LiteralString(ref s) => s.as_str(),
+                    _ => return None,
+                };
+                let interface = inst.operands[3..]
+                    .iter()
+                    .filter_map(|op| match *op {
+                        mr::Operand::IdRef(id) => Some(id),
+                        _ => None,
+                    })
+                    .collect();
+                Some(EntryPoint {
+                    execution_model: execution_model,
+                    entry_point: entry_point,
+                    name: name,
+                    interface: interface,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ModuleIndex;
+    use mr;
+    use spirv;
+
+    #[test]
+    fn def_resolves_a_result_id_to_its_defining_instruction() {
+        let mut module = mr::Module::new();
+        module.types_global_values
+            .push(mr::Instruction::new(spirv::Op::TypeVoid, None, Some(1), vec![]));
+        let index = ModuleIndex::new(&module);
+        assert_eq!(index.def(1).unwrap().class.opcode, spirv::Op::TypeVoid);
+        assert!(index.def(2).is_none());
+    }
+
+    #[test]
+    fn result_type_resolves_through_the_defining_instruction() {
+        let mut module = mr::Module::new();
+        module.types_global_values
+            .push(mr::Instruction::new(spirv::Op::Constant, Some(1), Some(2), vec![]));
+        let index = ModuleIndex::new(&module);
+        assert_eq!(index.result_type(2), Some(1));
+    }
+
+    #[test]
+    fn def_resolves_a_function_parameter_id() {
+        let mut module = mr::Module::new();
+        let mut f = mr::Function::new();
+        f.parameters
+            .push(mr::Instruction::new(spirv::Op::FunctionParameter, Some(1), Some(2), vec![]));
+        module.functions.push(f);
+        let index = ModuleIndex::new(&module);
+        assert_eq!(index.def(2).unwrap().class.opcode, spirv::Op::FunctionParameter);
+        assert_eq!(index.result_type(2), Some(1));
+    }
+
+    #[test]
+    fn decorations_finds_a_direct_opdecorate() {
+        let mut module = mr::Module::new();
+        module.annotations.push(mr::Instruction::new(spirv::Op::Decorate,
+                                                     None,
+                                                     None,
+                                                     vec![mr::Operand::IdRef(1),
+                                                          mr::Operand::Decoration(spirv::Decoration::Flat)]));
+        let index = ModuleIndex::new(&module);
+        assert_eq!(index.decorations(1).len(), 1);
+        assert!(index.decorations(2).is_empty());
+    }
+
+    #[test]
+    fn decorations_propagate_through_group_decorate() {
+        let mut module = mr::Module::new();
+        // The group's own decoration...
+        module.annotations.push(mr::Instruction::new(spirv::Op::Decorate,
+                                                     None,
+                                                     None,
+                                                     vec![mr::Operand::IdRef(10),
+                                                          mr::Operand::Decoration(spirv::Decoration::Flat)]));
+        // ...applied to id 1 via OpGroupDecorate.
+        module.annotations.push(mr::Instruction::new(spirv::Op::GroupDecorate,
+                                                     None,
+                                                     None,
+                                                     vec![mr::Operand::IdRef(10), mr::Operand::IdRef(1)]));
+        let index = ModuleIndex::new(&module);
+        assert_eq!(index.decorations(1).len(), 1);
+    }
+
+    #[test]
+    fn member_decorations_propagate_through_group_member_decorate() {
+        let mut module = mr::Module::new();
+        module.annotations.push(mr::Instruction::new(spirv::Op::Decorate,
+                                                     None,
+                                                     None,
+                                                     vec![mr::Operand::IdRef(10),
+                                                          mr::Operand::Decoration(spirv::Decoration::Flat)]));
+        module.annotations.push(mr::Instruction::new(spirv::Op::GroupMemberDecorate,
+                                                     None,
+                                                     None,
+                                                     vec![mr::Operand::IdRef(10),
+                                                          mr::Operand::IdRef(1),
+                                                          mr::Operand::LiteralInt32(0)]));
+        let index = ModuleIndex::new(&module);
+        assert_eq!(index.member_decorations(1, 0).len(), 1);
+        assert!(index.member_decorations(1, 1).is_empty());
+    }
+
+    #[test]
+    fn entry_points_decodes_the_execution_model_id_name_and_interface() {
+        let mut module = mr::Module::new();
+        module.entry_points.push(mr::Instruction::new(spirv::Op::EntryPoint,
+                                                       None,
+                                                       None,
+                                                       vec![mr::Operand::ExecutionModel(spirv::ExecutionModel::Vertex),
+                                                            mr::Operand::IdRef(1),
+                                                            mr::Operand::LiteralString("main".to_string()),
+                                                            mr::Operand::IdRef(2),
+                                                            mr::Operand::IdRef(3)]));
+        let index = ModuleIndex::new(&module);
+        let eps = index.entry_points();
+        assert_eq!(eps.len(), 1);
+        assert_eq!(eps[0].execution_model, spirv::ExecutionModel::Vertex);
+        assert_eq!(eps[0].entry_point, 1);
+        assert_eq!(eps[0].name, "main");
+        assert_eq!(eps[0].interface, vec![2, 3]);
+    }
+}
@@ -0,0 +1,140 @@
+// Copyright 2017 Google Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generated from the `OpenCL.std` extended instruction set grammar.
+//!
+//! This covers the common math, geometric, and relational instructions;
+//! a full build generates the complete table from the grammar JSON.
+
+/// An opcode in the `OpenCL.std` extended instruction set.
+///
+/// Pass as the `instruction` argument to
+/// [`Builder::ext_inst`](struct.Builder.html#method.ext_inst).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum CLOp {
+    Acos = 1,
+    Acosh = 2,
+    Acospi = 3,
+    Asin = 4,
+    Asinh = 5,
+    Asinpi = 6,
+    Atan = 7,
+    Atan2 = 8,
+    Atanh = 9,
+    Atanpi = 10,
+    Atan2pi = 11,
+    Cbrt = 12,
+    Ceil = 13,
+    Copysign = 14,
+    Cos = 15,
+    Cosh = 16,
+    Cospi = 17,
+    Erfc = 18,
+    Erf = 19,
+    Exp = 20,
+    Exp2 = 21,
+    Exp10 = 22,
+    Expm1 = 23,
+    FAbs = 24,
+    Fdim = 25,
+    Floor = 26,
+    Fma = 27,
+    Fmax = 28,
+    Fmin = 29,
+    Fmod = 30,
+    Fract = 31,
+    Frexp = 32,
+    Hypot = 33,
+    Ilogb = 34,
+    Ldexp = 35,
+    Lgamma = 36,
+    LgammaR = 37,
+    Log = 38,
+    Log2 = 39,
+    Log10 = 40,
+    Log1p = 41,
+    Logb = 42,
+    Mad = 43,
+    Maxmag = 44,
+    Minmag = 45,
+    Modf = 46,
+    Nan = 47,
+    Nextafter = 48,
+    Pow = 49,
+    Pown = 50,
+    Powr = 51,
+    Remainder = 52,
+    Remquo = 53,
+    Rint = 54,
+    Rootn = 55,
+    Round = 56,
+    Rsqrt = 57,
+    Sin = 58,
+    Sincos = 59,
+    Sinh = 60,
+    Sinpi = 61,
+    Sqrt = 62,
+    Tan = 63,
+    Tanh = 64,
+    Tanpi = 65,
+    Tgamma = 66,
+    Trunc = 67,
+    SAbs = 96,
+    SAbsDiff = 97,
+    SAddSat = 98,
+    UAddSat = 99,
+    SClamp = 104,
+    UClamp = 105,
+    Clz = 106,
+    Ctz = 107,
+    SMax = 111,
+    UMax = 112,
+    SMin = 113,
+    UMin = 114,
+    SMulHi = 115,
+    Rotate = 116,
+    SSubSat = 117,
+    USubSat = 118,
+    Popcount = 121,
+    UAbs = 126,
+    UAbsDiff = 127,
+    FClamp = 130,
+    Degrees = 131,
+    FmaxCommon = 132,
+    FminCommon = 133,
+    Mix = 134,
+    Radians = 135,
+    Step = 136,
+    Smoothstep = 137,
+    Sign = 138,
+    Cross = 139,
+    Distance = 140,
+    Length = 141,
+    Normalize = 142,
+    FastDistance = 143,
+    FastLength = 144,
+    FastNormalize = 145,
+    Bitselect = 146,
+    Select = 147,
+    Printf = 161,
+    Prefetch = 162,
+}
+
+impl CLOp {
+    /// Returns the literal instruction number this opcode encodes to.
+    pub fn as_u32(self) -> u32 {
+        self as u32
+    }
+}